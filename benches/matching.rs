@@ -0,0 +1,41 @@
+//! Compares request-matching throughput with and without the
+//! `object-pooling` feature. `data/easylist.to/...` isn't vendored in this
+//! tree (see `tests/ublock-coverage.rs`), so this benchmark stands in a
+//! synthetic list of the same rough shape and size as EasyList instead.
+//!
+//! Run `cargo bench` for the baseline, then `cargo bench --features
+//! object-pooling` and compare the two `matching/check_network_urls`
+//! numbers.
+
+use adblock::engine::Engine;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Bare single-word literal patterns (e.g. `adsN`), rather than `||host^`
+/// anchors, so that matching actually reaches the `RegexManager::matches_token`
+/// path `object-pooling` speeds up, instead of being short-circuited by
+/// `NetworkFilter::matches_pattern`'s hostname gate.
+fn synthetic_easylist(filter_count: usize) -> Vec<String> {
+    (0..filter_count).map(|i| format!("ads{i}")).collect()
+}
+
+fn synthetic_requests(request_count: usize) -> Vec<String> {
+    (0..request_count)
+        .map(|i| format!("https://cdn{}.example.com/assets/ads{}.js", i % 50, i % 500))
+        .collect()
+}
+
+fn matching_benchmark(c: &mut Criterion) {
+    let engine = Engine::from_rules(&synthetic_easylist(5_000));
+    let requests = synthetic_requests(2_000);
+
+    c.bench_function("matching/check_network_urls", |b| {
+        b.iter(|| {
+            for url in &requests {
+                engine.check_network_urls(url, "https://example.com/", "script");
+            }
+        })
+    });
+}
+
+criterion_group!(benches, matching_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,415 @@
+//! Holds the parsed filter set and matches individual requests against it.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use crate::cosmetic_filter_cache::{CosmeticFilterCache, UrlSpecificResources};
+use crate::filters::cosmetic::CosmeticFilter;
+use crate::filters::network::NetworkFilter;
+use crate::regex_manager::{RegexManager, RegexManagerDiscardPolicy};
+use crate::request::Request;
+use crate::resources::ResourceStorage;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlockerOptions {
+    pub debug: bool,
+    pub enable_optimizations: bool,
+    pub load_cosmetic_filters: bool,
+    pub load_network_filters: bool,
+}
+
+/// The outcome of matching a [`Request`] against the loaded filter set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockerResult {
+    pub matched: bool,
+    /// Set when the matching (or exception-overriding) filter carried
+    /// `$important`.
+    pub important: bool,
+    /// A ready-to-use replacement resource, when a `$redirect` filter
+    /// matched.
+    pub redirect: Option<String>,
+    /// The raw text of the exception filter that prevented a match.
+    pub exception: Option<String>,
+    /// The raw text of the filter responsible for `matched`/`exception`/
+    /// `rewritten_url`.
+    pub filter: Option<String>,
+    /// Set when a `$removeparam` filter stripped tracking query
+    /// parameters from the request URL and no blocking/exception rule
+    /// took precedence. `matched` is `false` in this case: the request
+    /// should proceed, but against this URL instead of the original.
+    pub rewritten_url: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum BlockerError {
+    #[error("failed to serialize blocker state")]
+    SerializationError,
+    #[error("failed to deserialize blocker state")]
+    DeserializationError,
+}
+
+/// Network filters are (de)serialized as their original raw lines and
+/// re-parsed, rather than serializing `NetworkFilter` directly, since its
+/// compiled `regex::Regex` fields aren't `Serialize`. Re-parsing is also
+/// what keeps network matching identical between serialized and
+/// freshly-loaded engines. Cosmetic filters are not serialized.
+#[derive(Serialize, Deserialize)]
+struct SerializedBlocker {
+    lines: Vec<String>,
+    debug: bool,
+    enable_optimizations: bool,
+}
+
+pub struct Blocker {
+    filters: Vec<NetworkFilter>,
+    pub debug: bool,
+    pub enable_optimizations: bool,
+    resources: ResourceStorage,
+    cosmetic_cache: CosmeticFilterCache,
+    regex_manager: RegexManager,
+}
+
+/// A filter is a "block" candidate - something that can cause `matched:
+/// true` on its own - if it's not an exception, doesn't just rewrite the
+/// URL, and (when it carries `$redirect`) isn't the weaker
+/// `$redirect-rule` form that only supplies content for *another* block.
+fn is_blocking_filter(f: &NetworkFilter) -> bool {
+    !f.is_exception && f.removeparam.is_none() && !f.redirect_rule
+}
+
+/// A filter is a genuine network exception - something that can cancel an
+/// unrelated block via [`Blocker::check`]'s exception scan - only if its
+/// sole effect is disabling blocking. `$removeparam` only disables
+/// query-param rewriting and `$generichide` only disables generic cosmetic
+/// hiding for a matching request; neither must be treated as a blanket
+/// network exception.
+fn is_blocking_exception(f: &NetworkFilter) -> bool {
+    f.is_exception && f.removeparam.is_none() && !f.generic_hide
+}
+
+impl Blocker {
+    pub fn new(network_filters: Vec<NetworkFilter>, options: &BlockerOptions) -> Blocker {
+        // `enable_optimizations` would normally trigger a merge/dedupe
+        // pass over `network_filters`; this build has no optimizer yet,
+        // so it's accepted and stored for round-tripping but otherwise
+        // unused.
+        Blocker {
+            filters: network_filters,
+            debug: options.debug,
+            enable_optimizations: options.enable_optimizations,
+            resources: ResourceStorage::default(),
+            cosmetic_cache: CosmeticFilterCache::new(Vec::new()),
+            regex_manager: RegexManager::new(),
+        }
+    }
+
+    pub fn set_resources(&mut self, resources: ResourceStorage) {
+        self.resources = resources;
+    }
+
+    pub fn set_cosmetic_filters(&mut self, filters: Vec<CosmeticFilter>) {
+        self.cosmetic_cache = CosmeticFilterCache::new(filters);
+    }
+
+    /// Changes how aggressively the lazily-compiled regex cache is
+    /// trimmed; takes effect on the next [`Blocker::cleanup_regex_cache`]
+    /// call.
+    pub fn set_regex_discard_policy(&mut self, policy: RegexManagerDiscardPolicy) {
+        self.regex_manager.set_discard_policy(policy);
+    }
+
+    /// Runs a regex cache cleanup pass under the current discard policy.
+    /// Matching never evicts cached regexes on its own; the cache is only
+    /// trimmed when this is called.
+    pub fn cleanup_regex_cache(&self) {
+        self.regex_manager.cleanup();
+    }
+
+    /// Whether a matching `$generichide` exception suppresses
+    /// non-domain-specific cosmetic hide rules for `hostname`.
+    fn generic_hide_disabled(&self, hostname: &str) -> bool {
+        let synthetic_url = format!("https://{}/", hostname);
+        let request = Request::from_hostnames(&synthetic_url, hostname, "", "", None);
+        self.filters
+            .iter()
+            .any(|f| f.is_exception && f.generic_hide && f.matches(&request, &self.regex_manager))
+    }
+
+    /// Resolves the cosmetic hide selectors and scriptlets that should be
+    /// injected into a page at `url`.
+    pub fn url_cosmetic_resources(&self, url: &str) -> UrlSpecificResources {
+        let hostname = crate::url_parser::parse_url(url)
+            .and_then(|parsed| parsed.host_str().map(|s| s.to_string()))
+            .unwrap_or_default();
+        let generic_hide_disabled = self.generic_hide_disabled(&hostname);
+        self.cosmetic_cache
+            .hostname_cosmetic_resources(&hostname, generic_hide_disabled, &self.resources)
+    }
+
+    fn resolve_redirect(&self, filter: &NetworkFilter) -> Option<String> {
+        filter
+            .redirect
+            .as_deref()
+            .and_then(|name| self.resources.get_redirect_resource(name))
+    }
+
+    /// A `$redirect-rule` filter that matches `request`, used to find
+    /// substitute content for a block caused by some *other* filter.
+    fn matching_redirect_rule(&self, request: &Request) -> Option<&NetworkFilter> {
+        self.filters
+            .iter()
+            .find(|f| !f.is_exception && f.redirect_rule && f.matches(request, &self.regex_manager))
+    }
+
+    pub fn check(&self, request: &Request) -> BlockerResult {
+        if let Some(important) = self
+            .filters
+            .iter()
+            .find(|f| {
+                !f.is_exception
+                    && f.is_important
+                    && f.removeparam.is_none()
+                    && f.matches(request, &self.regex_manager)
+            })
+        {
+            return BlockerResult {
+                matched: true,
+                important: true,
+                filter: important.raw_line.clone(),
+                redirect: self.resolve_redirect(important),
+                ..Default::default()
+            };
+        }
+
+        let exception = self
+            .filters
+            .iter()
+            .find(|f| is_blocking_exception(f) && f.matches(request, &self.regex_manager));
+
+        if let Some(block) = self
+            .filters
+            .iter()
+            .find(|f| is_blocking_filter(f) && f.matches(request, &self.regex_manager))
+        {
+            return match exception {
+                Some(exception) => BlockerResult {
+                    matched: false,
+                    exception: exception.raw_line.clone(),
+                    filter: exception.raw_line.clone(),
+                    ..Default::default()
+                },
+                None => {
+                    let redirect = self
+                        .resolve_redirect(block)
+                        .or_else(|| self.matching_redirect_rule(request).and_then(|f| self.resolve_redirect(f)));
+                    BlockerResult {
+                        matched: true,
+                        important: block.is_important,
+                        filter: block.raw_line.clone(),
+                        redirect,
+                        ..Default::default()
+                    }
+                }
+            };
+        }
+
+        if exception.is_some() {
+            return BlockerResult {
+                matched: false,
+                exception: exception.and_then(|e| e.raw_line.clone()),
+                filter: exception.and_then(|e| e.raw_line.clone()),
+                ..Default::default()
+            };
+        }
+
+        if let Some(removeparam_filter) = self
+            .filters
+            .iter()
+            .find(|f| !f.is_exception && f.removeparam.is_some() && f.matches(request, &self.regex_manager))
+        {
+            // A matching `$removeparam` exception disables query-param
+            // rewriting for this request.
+            let rewrite_excepted = self.filters.iter().any(|f| {
+                f.is_exception && f.removeparam.is_some() && f.matches(request, &self.regex_manager)
+            });
+            if rewrite_excepted {
+                return BlockerResult::default();
+            }
+            if let Ok(url) = Url::parse(&request.url) {
+                if let Some(rewritten) = removeparam_filter
+                    .removeparam
+                    .as_ref()
+                    .and_then(|rp| rp.rewrite(&url))
+                {
+                    return BlockerResult {
+                        matched: false,
+                        rewritten_url: Some(rewritten),
+                        filter: removeparam_filter.raw_line.clone(),
+                        ..Default::default()
+                    };
+                }
+            }
+        }
+
+        BlockerResult::default()
+    }
+
+    fn raw_filter_lines(&self) -> Vec<String> {
+        self.filters.iter().filter_map(|f| f.raw_line.clone()).collect()
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, BlockerError> {
+        let serialized = SerializedBlocker {
+            lines: self.raw_filter_lines(),
+            debug: self.debug,
+            enable_optimizations: self.enable_optimizations,
+        };
+        serde_json::to_vec(&serialized).map_err(|_| BlockerError::SerializationError)
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Blocker, BlockerError> {
+        let serialized: SerializedBlocker =
+            serde_json::from_slice(data).map_err(|_| BlockerError::DeserializationError)?;
+        let filters = serialized
+            .lines
+            .iter()
+            .filter_map(|line| NetworkFilter::parse(line))
+            .collect();
+        // Resources are loaded separately via `Engine::use_resources` and
+        // aren't part of the serialized snapshot; callers that need
+        // redirects after deserializing should call it again. Cosmetic
+        // filters aren't part of it either, so a deserialized blocker
+        // starts with none.
+        Ok(Blocker {
+            filters,
+            debug: serialized.debug,
+            enable_optimizations: serialized.enable_optimizations,
+            resources: ResourceStorage::default(),
+            cosmetic_cache: CosmeticFilterCache::new(Vec::new()),
+            regex_manager: RegexManager::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocker_from(rules: &[&str]) -> Blocker {
+        let filters = rules.iter().filter_map(|r| NetworkFilter::parse(r)).collect();
+        Blocker::new(
+            filters,
+            &BlockerOptions {
+                debug: true,
+                enable_optimizations: false,
+                load_cosmetic_filters: false,
+                load_network_filters: true,
+            },
+        )
+    }
+
+    #[test]
+    fn important_overrides_exception() {
+        let blocker = blocker_from(&["||ads.example^$important", "@@||ads.example^"]);
+        let request = Request::new("https://ads.example/a.js", "", "").unwrap();
+        assert!(blocker.check(&request).matched);
+    }
+
+    #[test]
+    fn exception_overrides_plain_block() {
+        let blocker = blocker_from(&["||ads.example^", "@@||ads.example^"]);
+        let request = Request::new("https://ads.example/a.js", "", "").unwrap();
+        let result = blocker.check(&request);
+        assert!(!result.matched);
+        assert!(result.exception.is_some());
+    }
+
+    #[test]
+    fn removeparam_rewrites_when_nothing_else_matches() {
+        let blocker = blocker_from(&["||example.com^$removeparam=utm_source"]);
+        let request = Request::new(
+            "https://example.com/page?utm_source=newsletter&id=1",
+            "",
+            "",
+        )
+        .unwrap();
+        let result = blocker.check(&request);
+        assert!(!result.matched);
+        assert_eq!(
+            result.rewritten_url.as_deref(),
+            Some("https://example.com/page?id=1")
+        );
+    }
+
+    #[test]
+    fn block_takes_precedence_over_removeparam() {
+        let blocker = blocker_from(&[
+            "||example.com^$removeparam=utm_source",
+            "||example.com/page^",
+        ]);
+        let request = Request::new("https://example.com/page?utm_source=x", "", "").unwrap();
+        let result = blocker.check(&request);
+        assert!(result.matched);
+        assert!(result.rewritten_url.is_none());
+    }
+
+    fn noop_resource() -> crate::resources::Resource {
+        crate::resources::Resource {
+            name: "noop.js".to_string(),
+            aliases: vec![],
+            kind: crate::resources::MimeType::ApplicationJavascript,
+            content: crate::resources::ResourceContent::Raw("(function(){})()".to_string()),
+        }
+    }
+
+    #[test]
+    fn redirect_filter_blocks_and_substitutes() {
+        let mut blocker = blocker_from(&["||example.com/tracker.js$redirect=noop.js"]);
+        blocker.set_resources(ResourceStorage::from_resources(vec![noop_resource()]));
+        let request = Request::new("https://example.com/tracker.js", "", "").unwrap();
+        let result = blocker.check(&request);
+        assert!(result.matched);
+        assert!(result.redirect.unwrap().starts_with("data:application/javascript;base64,"));
+    }
+
+    #[test]
+    fn redirect_rule_only_substitutes_when_another_filter_blocks() {
+        let mut blocker = blocker_from(&[
+            "||example.com/tracker.js$redirect-rule=noop.js",
+            "||example.com/tracker.js",
+        ]);
+        blocker.set_resources(ResourceStorage::from_resources(vec![noop_resource()]));
+        let request = Request::new("https://example.com/tracker.js", "", "").unwrap();
+        let result = blocker.check(&request);
+        assert!(result.matched);
+        assert!(result.redirect.is_some());
+    }
+
+    #[test]
+    fn redirect_rule_alone_does_not_block() {
+        let mut blocker = blocker_from(&["||example.com/tracker.js$redirect-rule=noop.js"]);
+        blocker.set_resources(ResourceStorage::from_resources(vec![noop_resource()]));
+        let request = Request::new("https://example.com/tracker.js", "", "").unwrap();
+        let result = blocker.check(&request);
+        assert!(!result.matched);
+        assert!(result.redirect.is_none());
+    }
+
+    #[test]
+    fn removeparam_exception_does_not_cancel_unrelated_block() {
+        let blocker = blocker_from(&[
+            "||ads.example.com^",
+            "@@||ads.example.com^$removeparam=utm_source",
+        ]);
+        let request = Request::new("https://ads.example.com/a.js", "", "").unwrap();
+        assert!(blocker.check(&request).matched);
+    }
+
+    #[test]
+    fn generichide_exception_does_not_cancel_unrelated_block() {
+        let blocker = blocker_from(&["||ads.example.com^", "@@||ads.example.com^$generichide"]);
+        let request = Request::new("https://ads.example.com/a.js", "", "").unwrap();
+        assert!(blocker.check(&request).matched);
+    }
+}
@@ -0,0 +1,108 @@
+//! Builds an [`Engine`] from a remote Brave-style `list_catalog.json`
+//! instead of local files, fetching every list it references concurrently.
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::engine::Engine;
+
+#[derive(Debug, Deserialize)]
+struct CatalogSource {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogComponent {
+    #[serde(default)]
+    sources: Vec<CatalogSource>,
+}
+
+#[derive(Error, Debug)]
+pub enum CatalogError {
+    #[error("failed to fetch {url}: {message}")]
+    Fetch { url: String, message: String },
+    #[error("failed to parse catalog: {0}")]
+    Parse(String),
+}
+
+/// Flattens a `list_catalog.json` document down to the list of source
+/// URLs every component references, in document order.
+fn source_urls(catalog_json: &str) -> Result<Vec<String>, CatalogError> {
+    let components: Vec<CatalogComponent> =
+        serde_json::from_str(catalog_json).map_err(|e| CatalogError::Parse(e.to_string()))?;
+    Ok(components
+        .into_iter()
+        .flat_map(|component| component.sources.into_iter().map(|source| source.url))
+        .collect())
+}
+
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, CatalogError> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| CatalogError::Fetch { url: url.to_string(), message: e.to_string() })?
+        .text()
+        .await
+        .map_err(|e| CatalogError::Fetch { url: url.to_string(), message: e.to_string() })
+}
+
+impl Engine {
+    /// Fetches the `list_catalog.json` at `catalog_url`, downloads every
+    /// list it references concurrently, and builds an `Engine` from their
+    /// concatenated rules.
+    pub async fn from_catalog_url(catalog_url: &str) -> Result<Engine, CatalogError> {
+        let client = reqwest::Client::new();
+        let catalog_text = fetch_text(&client, catalog_url).await?;
+        let urls = source_urls(&catalog_text)?;
+
+        let downloads = urls.into_iter().map(|url| {
+            let client = client.clone();
+            tokio::spawn(async move { fetch_text(&client, &url).await })
+        });
+
+        let mut rules = Vec::new();
+        for download in downloads {
+            let text = download.await.map_err(|e| CatalogError::Fetch {
+                url: "<unknown>".to_string(),
+                message: e.to_string(),
+            })??;
+            rules.extend(text.lines().map(|line| line.to_string()));
+        }
+
+        Ok(Engine::from_rules(&rules))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_source_urls_from_every_component() {
+        let catalog = r#"[
+            {"sources": [{"url": "https://example.com/a.txt"}]},
+            {"sources": [{"url": "https://example.com/b.txt"}, {"url": "https://example.com/c.txt"}]}
+        ]"#;
+        assert_eq!(
+            source_urls(catalog).unwrap(),
+            vec![
+                "https://example.com/a.txt".to_string(),
+                "https://example.com/b.txt".to_string(),
+                "https://example.com/c.txt".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn components_without_sources_contribute_nothing() {
+        let catalog = r#"[{"name": "empty"}]"#;
+        assert_eq!(source_urls(catalog).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn rejects_malformed_catalog_json() {
+        assert!(source_urls("not json").is_err());
+    }
+}
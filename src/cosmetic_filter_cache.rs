@@ -0,0 +1,130 @@
+//! Resolves the cosmetic filter set down to the concrete hide selectors
+//! and scriptlet sources a page at a given URL should be injected with.
+
+use std::collections::HashSet;
+
+use crate::filters::cosmetic::CosmeticFilter;
+use crate::resources::ResourceStorage;
+
+/// What [`crate::engine::Engine::url_cosmetic_resources`] hands back for a
+/// page: CSS selectors to hide, and ready-to-inject scriptlet source.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UrlSpecificResources {
+    pub hide_selectors: HashSet<String>,
+    pub scriptlets: Vec<String>,
+}
+
+fn render_scriptlet(resources: &ResourceStorage, scriptlet: &crate::filters::cosmetic::ScriptletInjection) -> Option<String> {
+    let resource = resources.get(&scriptlet.name)?;
+    let mut source = resource.decoded_content()?;
+    for (i, arg) in scriptlet.args.iter().enumerate() {
+        source = source.replace(&format!("{{{{{}}}}}", i + 1), arg);
+    }
+    Some(source)
+}
+
+pub struct CosmeticFilterCache {
+    filters: Vec<CosmeticFilter>,
+}
+
+impl CosmeticFilterCache {
+    pub fn new(filters: Vec<CosmeticFilter>) -> CosmeticFilterCache {
+        CosmeticFilterCache { filters }
+    }
+
+    /// Resolves hide selectors and scriptlets for a page at `hostname`.
+    /// `generic_hide_disabled` mirrors a matching `$generichide`
+    /// exception: non-domain-specific hide rules are skipped when set.
+    pub fn hostname_cosmetic_resources(
+        &self,
+        hostname: &str,
+        generic_hide_disabled: bool,
+        resources: &ResourceStorage,
+    ) -> UrlSpecificResources {
+        let mut hide_selectors = HashSet::new();
+        let mut scriptlets = Vec::new();
+
+        for filter in &self.filters {
+            if !filter.matches_hostname(hostname) {
+                continue;
+            }
+            if filter.is_unhide {
+                continue;
+            }
+            if let Some(scriptlet) = &filter.scriptlet {
+                if let Some(rendered) = render_scriptlet(resources, scriptlet) {
+                    scriptlets.push(rendered);
+                }
+            } else if let Some(selector) = &filter.selector {
+                if !filter.is_generic() || !generic_hide_disabled {
+                    hide_selectors.insert(selector.clone());
+                }
+            }
+        }
+
+        // A second pass for `#@#` unhide rules, so that they can remove a
+        // selector added by a rule earlier in the list regardless of
+        // ordering between the two.
+        for filter in &self.filters {
+            if filter.is_unhide && filter.matches_hostname(hostname) {
+                if let Some(selector) = &filter.selector {
+                    hide_selectors.remove(selector);
+                }
+            }
+        }
+
+        UrlSpecificResources {
+            hide_selectors,
+            scriptlets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{MimeType, Resource, ResourceContent};
+
+    fn cache(rules: &[&str]) -> CosmeticFilterCache {
+        CosmeticFilterCache::new(rules.iter().filter_map(|r| CosmeticFilter::parse(r)).collect())
+    }
+
+    #[test]
+    fn resolves_domain_specific_hide() {
+        let cache = cache(&["example.com##.ad-banner"]);
+        let resources = ResourceStorage::default();
+        let result = cache.hostname_cosmetic_resources("example.com", false, &resources);
+        assert!(result.hide_selectors.contains(".ad-banner"));
+    }
+
+    #[test]
+    fn generichide_suppresses_generic_rules_only() {
+        let cache = cache(&["##.generic-ad", "example.com##.specific-ad"]);
+        let resources = ResourceStorage::default();
+        let result = cache.hostname_cosmetic_resources("example.com", true, &resources);
+        assert!(!result.hide_selectors.contains(".generic-ad"));
+        assert!(result.hide_selectors.contains(".specific-ad"));
+    }
+
+    #[test]
+    fn unhide_removes_selector() {
+        let cache = cache(&["##.ad-banner", "example.com#@#.ad-banner"]);
+        let resources = ResourceStorage::default();
+        let result = cache.hostname_cosmetic_resources("example.com", false, &resources);
+        assert!(!result.hide_selectors.contains(".ad-banner"));
+    }
+
+    #[test]
+    fn resolves_scriptlet_with_arg_templating() {
+        let cache = cache(&["b.com##+js(hjt, arg)"]);
+        let mut resources = vec![Resource {
+            name: "hijacktest.js".to_string(),
+            aliases: vec!["hjt".to_string()],
+            kind: MimeType::ApplicationJavascript,
+            content: ResourceContent::Raw("console.log('{{1}}')".to_string()),
+        }];
+        let storage = ResourceStorage::from_resources(std::mem::take(&mut resources));
+        let result = cache.hostname_cosmetic_resources("b.com", false, &storage);
+        assert_eq!(result.scriptlets, vec!["console.log('arg')".to_string()]);
+    }
+}
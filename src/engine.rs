@@ -0,0 +1,76 @@
+//! The public, high-level entry point: load a filter list once, then check
+//! requests against it.
+
+use crate::blocker::{Blocker, BlockerError, BlockerOptions, BlockerResult};
+use crate::cosmetic_filter_cache::UrlSpecificResources;
+use crate::lists::parse_filters;
+use crate::request::Request;
+use crate::resources::{Resource, ResourceStorage};
+
+pub struct Engine {
+    pub blocker: Blocker,
+}
+
+impl Engine {
+    fn from_rules_with_options(rules: &[String], debug: bool) -> Engine {
+        let (network_filters, cosmetic_filters) = parse_filters(rules, true, true, debug);
+        let options = BlockerOptions {
+            debug,
+            enable_optimizations: false,
+            load_cosmetic_filters: true,
+            load_network_filters: true,
+        };
+        let mut blocker = Blocker::new(network_filters, &options);
+        blocker.set_cosmetic_filters(cosmetic_filters);
+        Engine { blocker }
+    }
+
+    pub fn from_rules(rules: &[String]) -> Engine {
+        Engine::from_rules_with_options(rules, false)
+    }
+
+    pub fn from_rules_debug(rules: &[String]) -> Engine {
+        Engine::from_rules_with_options(rules, true)
+    }
+
+    pub fn check_network_urls(&self, url: &str, source_url: &str, request_type: &str) -> BlockerResult {
+        match Request::new(url, source_url, request_type) {
+            Ok(request) => self.blocker.check(&request),
+            Err(_) => BlockerResult::default(),
+        }
+    }
+
+    pub fn check_network_urls_with_hostnames(
+        &self,
+        url: &str,
+        hostname: &str,
+        source_hostname: &str,
+        request_type: &str,
+        third_party: Option<bool>,
+    ) -> BlockerResult {
+        let request = Request::from_hostnames(url, hostname, source_hostname, request_type, third_party);
+        self.blocker.check(&request)
+    }
+
+    /// Loads a resource bundle (e.g. uBlock's `resources.json`) so that
+    /// `$redirect`/`$redirect-rule` filters can be resolved to actual
+    /// substitute content.
+    pub fn use_resources(&mut self, resources: Vec<Resource>) {
+        self.blocker.set_resources(ResourceStorage::from_resources(resources));
+    }
+
+    /// Returns the cosmetic hide selectors and scriptlet sources to
+    /// inject into the page at `url`.
+    pub fn url_cosmetic_resources(&self, url: &str) -> UrlSpecificResources {
+        self.blocker.url_cosmetic_resources(url)
+    }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, BlockerError> {
+        self.blocker.serialize()
+    }
+
+    pub fn deserialize(&mut self, data: &[u8]) -> Result<(), BlockerError> {
+        self.blocker = Blocker::deserialize(data)?;
+        Ok(())
+    }
+}
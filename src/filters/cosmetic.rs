@@ -0,0 +1,123 @@
+//! Parsing of cosmetic filter lines: element hiding (`##`), unhiding
+//! (`#@#`), and scriptlet injection (`##+js(...)`).
+
+use crate::filters::domain::{matches_domain_options, parse_domain_list, DomainOption};
+
+/// A `##+js(name, arg1, arg2)` scriptlet reference, resolved against
+/// [`crate::resources::ResourceStorage`] at `url_cosmetic_resources` time.
+#[derive(Debug, Clone)]
+pub struct ScriptletInjection {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+fn parse_scriptlet(call: &str) -> Option<ScriptletInjection> {
+    let inner = call.strip_prefix("+js(")?.strip_suffix(')')?;
+    let mut parts = inner.split(',').map(|s| s.trim().to_string());
+    let name = parts.next().filter(|s| !s.is_empty())?;
+    let args = parts.filter(|s| !s.is_empty()).collect();
+    Some(ScriptletInjection { name, args })
+}
+
+#[derive(Debug, Clone)]
+pub struct CosmeticFilter {
+    pub raw_line: Option<String>,
+    /// `true` for `#@#` (unhide) rules.
+    pub is_unhide: bool,
+    pub domains: Vec<DomainOption>,
+    /// The CSS selector to hide/unhide, for plain cosmetic rules.
+    pub selector: Option<String>,
+    /// Set instead of `selector` for `##+js(...)` rules.
+    pub scriptlet: Option<ScriptletInjection>,
+}
+
+impl CosmeticFilter {
+    /// Parses one cosmetic filter line, e.g. `example.com##.ad-banner` or
+    /// `##+js(noop)`. Returns `None` for lines that aren't cosmetic rules
+    /// at all.
+    pub fn parse(line: &str) -> Option<CosmeticFilter> {
+        let line = line.trim();
+
+        let (domains_part, body, is_unhide) = if let Some(idx) = line.find("#@#") {
+            (&line[..idx], &line[idx + 3..], true)
+        } else if let Some(idx) = line.find("##") {
+            (&line[..idx], &line[idx + 2..], false)
+        } else {
+            return None;
+        };
+
+        let domains = parse_domain_list(domains_part, ',');
+
+        let (selector, scriptlet) = match parse_scriptlet(body) {
+            Some(scriptlet) => (None, Some(scriptlet)),
+            None if !body.is_empty() => (Some(body.to_string()), None),
+            None => (None, None),
+        };
+
+        if selector.is_none() && scriptlet.is_none() {
+            return None;
+        }
+
+        Some(CosmeticFilter {
+            raw_line: Some(line.to_string()),
+            is_unhide,
+            domains,
+            selector,
+            scriptlet,
+        })
+    }
+
+    pub fn is_generic(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    pub fn matches_hostname(&self, hostname: &str) -> bool {
+        matches_domain_options(hostname, &self.domains)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_domain_specific_hide() {
+        let filter = CosmeticFilter::parse("example.com##.ad-banner").unwrap();
+        assert!(!filter.is_unhide);
+        assert!(!filter.is_generic());
+        assert_eq!(filter.selector.as_deref(), Some(".ad-banner"));
+    }
+
+    #[test]
+    fn parses_generic_hide() {
+        let filter = CosmeticFilter::parse("##.ad-banner").unwrap();
+        assert!(filter.is_generic());
+    }
+
+    #[test]
+    fn parses_unhide() {
+        let filter = CosmeticFilter::parse("example.com#@#.ad-banner").unwrap();
+        assert!(filter.is_unhide);
+    }
+
+    #[test]
+    fn parses_scriptlet_with_args() {
+        let filter = CosmeticFilter::parse("b.com##+js(hjt, arg)").unwrap();
+        let scriptlet = filter.scriptlet.unwrap();
+        assert_eq!(scriptlet.name, "hjt");
+        assert_eq!(scriptlet.args, vec!["arg".to_string()]);
+    }
+
+    #[test]
+    fn parses_scriptlet_without_args() {
+        let filter = CosmeticFilter::parse("##+js(noop)").unwrap();
+        let scriptlet = filter.scriptlet.unwrap();
+        assert_eq!(scriptlet.name, "noop");
+        assert!(scriptlet.args.is_empty());
+    }
+
+    #[test]
+    fn non_cosmetic_lines_are_not_parsed() {
+        assert!(CosmeticFilter::parse("||doubleclick.net^").is_none());
+    }
+}
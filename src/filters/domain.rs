@@ -0,0 +1,69 @@
+//! Shared `domain=`/prefix restriction parsing, used by both network and
+//! cosmetic filters.
+
+/// One entry from a `domain1,~domain2` (cosmetic) or `domain=d1|~d2`
+/// (network) restriction: a hostname the filter either requires
+/// (`excluded == false`) or forbids (`excluded == true`, written with a
+/// leading `~`).
+#[derive(Debug, Clone)]
+pub struct DomainOption {
+    pub domain: String,
+    pub excluded: bool,
+}
+
+pub fn parse_domain_list(value: &str, separator: char) -> Vec<DomainOption> {
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|token| match token.strip_prefix('~') {
+            Some(domain) => DomainOption {
+                domain: domain.to_string(),
+                excluded: true,
+            },
+            None => DomainOption {
+                domain: token.to_string(),
+                excluded: false,
+            },
+        })
+        .collect()
+}
+
+pub fn hostname_matches_domain(hostname: &str, domain: &str) -> bool {
+    hostname == domain || hostname.ends_with(&format!(".{}", domain))
+}
+
+/// Applies a list of [`DomainOption`]s the way filter syntax does: any
+/// exclusion match disqualifies the hostname outright; otherwise, if any
+/// inclusions are present, at least one must match.
+pub fn matches_domain_options(hostname: &str, domains: &[DomainOption]) -> bool {
+    if domains.is_empty() {
+        return true;
+    }
+    let (included, excluded): (Vec<_>, Vec<_>) = domains.iter().partition(|d| !d.excluded);
+
+    if excluded.iter().any(|d| hostname_matches_domain(hostname, &d.domain)) {
+        return false;
+    }
+    included.is_empty() || included.iter().any(|d| hostname_matches_domain(hostname, &d.domain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mixed_inclusion_exclusion() {
+        let domains = parse_domain_list("foo.com|~bar.com", '|');
+        assert_eq!(domains.len(), 2);
+        assert!(!domains[0].excluded);
+        assert!(domains[1].excluded);
+    }
+
+    #[test]
+    fn exclusion_wins_over_inclusion() {
+        let domains = parse_domain_list("example.com|~ads.example.com", '|');
+        assert!(!matches_domain_options("ads.example.com", &domains));
+        assert!(matches_domain_options("example.com", &domains));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod cosmetic;
+pub mod domain;
+pub mod network;
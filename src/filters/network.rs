@@ -0,0 +1,405 @@
+//! Parsing and matching of single network filter rules, e.g.
+//! `||doubleclick.net^$third-party` or `@@||example.com/ads.js$script`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use regex::Regex;
+use url::form_urlencoded;
+
+use crate::filters::domain::{hostname_matches_domain, matches_domain_options, parse_domain_list, DomainOption};
+use crate::regex_manager::RegexManager;
+use crate::request::Request;
+
+/// Restricts which param names a `$removeparam` filter strips.
+#[derive(Debug, Clone)]
+pub enum RemoveParamMatcher {
+    Name(String),
+    Regex(Regex),
+}
+
+/// Parsed form of a `$removeparam=<value>` option.
+///
+/// `invert` is set when the option value is written `~name`: instead of
+/// stripping the named parameter, every parameter *except* it is stripped.
+#[derive(Debug, Clone)]
+pub struct RemoveParamFilter {
+    pub matcher: RemoveParamMatcher,
+    pub invert: bool,
+}
+
+/// Parameter names consist of word characters and dashes; this rejects
+/// values that are clearly not valid query keys before we ever try to
+/// match against one.
+fn valid_param_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl RemoveParamFilter {
+    pub fn parse(value: &str) -> Option<RemoveParamFilter> {
+        let (invert, value) = match value.strip_prefix('~') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        if value.len() >= 2 && value.starts_with('/') && value.ends_with('/') {
+            let pattern = &value[1..value.len() - 1];
+            let regex = Regex::new(pattern).ok()?;
+            Some(RemoveParamFilter {
+                matcher: RemoveParamMatcher::Regex(regex),
+                invert,
+            })
+        } else if valid_param_name(value) {
+            Some(RemoveParamFilter {
+                matcher: RemoveParamMatcher::Name(value.to_string()),
+                invert,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        let matched = match &self.matcher {
+            RemoveParamMatcher::Name(expected) => name == expected,
+            RemoveParamMatcher::Regex(re) => re.is_match(name),
+        };
+        if self.invert {
+            !matched
+        } else {
+            matched
+        }
+    }
+
+    /// Returns the query-stripped form of `url`, or `None` if nothing
+    /// needed to change (no query string, or no matching param present).
+    pub fn rewrite(&self, url: &url::Url) -> Option<String> {
+        url.query()?;
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !self.matches_name(name))
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+
+        let original_count = url.query_pairs().count();
+        if kept.len() == original_count {
+            return None;
+        }
+
+        let mut rewritten = url.clone();
+        if kept.is_empty() {
+            rewritten.set_query(None);
+        } else {
+            let mut serializer = form_urlencoded::Serializer::new(String::new());
+            for (k, v) in &kept {
+                serializer.append_pair(k, v);
+            }
+            rewritten.set_query(Some(&serializer.finish()));
+        }
+        Some(rewritten.into())
+    }
+}
+
+/// Assigns each parsed filter a process-wide unique id, used as the
+/// [`RegexManager`] cache key so lazily-compiled regexes can be looked up
+/// (and evicted) per filter.
+static NEXT_FILTER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A single parsed line from a filter list.
+#[derive(Debug, Clone)]
+pub struct NetworkFilter {
+    pub id: u64,
+    /// The original, unparsed line, kept around for debugging and for the
+    /// `filter` field callers see on a match.
+    pub raw_line: Option<String>,
+    pub is_exception: bool,
+    pub is_important: bool,
+    /// `Some(true)` = third-party only, `Some(false)` = first-party only,
+    /// `None` = no restriction.
+    pub third_party: Option<bool>,
+    /// Set for `||hostname^`-anchored filters; matches the request's
+    /// hostname or any of its subdomains.
+    pub hostname: Option<String>,
+    /// The remaining literal/wildcard pattern matched against the full
+    /// URL, after the hostname anchor (if any) is stripped off. Matched
+    /// via [`RegexManager`], which compiles it to a `Regex` lazily (only
+    /// patterns that need one - see [`crate::regex_manager::needs_regex`] -
+    /// are ever compiled at all).
+    pub pattern: String,
+    resource_types: Vec<String>,
+    excluded_resource_types: Vec<String>,
+    domains: Vec<DomainOption>,
+    pub removeparam: Option<RemoveParamFilter>,
+    /// The resource name from a `$redirect=` or `$redirect-rule=` option.
+    pub redirect: Option<String>,
+    /// `true` if `redirect` came from `$redirect-rule` rather than plain
+    /// `$redirect`: the substitute resource only applies when some other
+    /// filter also blocks the request, rather than blocking it itself.
+    pub redirect_rule: bool,
+    /// Set by `$generichide`: an exception filter carrying this
+    /// suppresses non-domain-specific cosmetic hide rules on matching
+    /// pages (see [`crate::cosmetic_filter_cache::CosmeticFilterCache`]).
+    pub generic_hide: bool,
+}
+
+const KNOWN_RESOURCE_TYPES: &[&str] = &[
+    "script",
+    "image",
+    "stylesheet",
+    "object",
+    "xmlhttprequest",
+    "subdocument",
+    "document",
+    "websocket",
+    "webrtc",
+    "ping",
+    "other",
+    "media",
+    "font",
+    "popup",
+];
+
+impl NetworkFilter {
+    /// Parses one line from a filter list. Returns `None` for blank lines,
+    /// comments (`!...`), and Adblock Plus metadata headers (`[...]`).
+    pub fn parse(line: &str) -> Option<NetworkFilter> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+            return None;
+        }
+
+        let (is_exception, rest) = match line.strip_prefix("@@") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        // Split the pattern from its `$`-delimited options. This is a
+        // simplification of the real grammar (which must be careful about
+        // `$` inside inline regex literals); good enough for the patterns
+        // seen in practice.
+        let (pattern_part, options_part) = match rest.rfind('$') {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+            None => (rest, None),
+        };
+
+        let mut filter = NetworkFilter {
+            id: NEXT_FILTER_ID.fetch_add(1, Ordering::Relaxed),
+            raw_line: Some(line.to_string()),
+            is_exception,
+            is_important: false,
+            third_party: None,
+            hostname: None,
+            pattern: String::new(),
+            resource_types: Vec::new(),
+            excluded_resource_types: Vec::new(),
+            domains: Vec::new(),
+            removeparam: None,
+            redirect: None,
+            redirect_rule: false,
+            generic_hide: false,
+        };
+
+        if let Some(options) = options_part {
+            for option in options.split(',') {
+                let option = option.trim();
+                if option.is_empty() {
+                    continue;
+                }
+                let (negated, option) = match option.strip_prefix('~') {
+                    Some(rest) => (true, rest),
+                    None => (false, option),
+                };
+                if let Some((key, value)) = option.split_once('=') {
+                    match key {
+                        "domain" => filter.domains = parse_domain_list(value, '|'),
+                        "removeparam" => {
+                            let value = if negated {
+                                format!("~{}", value)
+                            } else {
+                                value.to_string()
+                            };
+                            filter.removeparam = RemoveParamFilter::parse(&value);
+                        }
+                        "redirect" => filter.redirect = Some(value.to_string()),
+                        "redirect-rule" => {
+                            filter.redirect = Some(value.to_string());
+                            filter.redirect_rule = true;
+                        }
+                        _ => {}
+                    }
+                } else {
+                    match option {
+                        "third-party" | "3p" => filter.third_party = Some(!negated),
+                        "first-party" | "1p" => filter.third_party = Some(negated),
+                        "important" => filter.is_important = true,
+                        "generichide" => filter.generic_hide = true,
+                        "removeparam" => {} // no bare value to act on
+                        resource_type if KNOWN_RESOURCE_TYPES.contains(&resource_type) => {
+                            if negated {
+                                filter.excluded_resource_types.push(resource_type.to_string());
+                            } else {
+                                filter.resource_types.push(resource_type.to_string());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if let Some(hostname_part) = pattern_part.strip_prefix("||") {
+            let end = hostname_part
+                .find(['^', '/', '*'])
+                .unwrap_or(hostname_part.len());
+            let (hostname, remainder) = hostname_part.split_at(end);
+            filter.hostname = Some(hostname.to_lowercase());
+            // `^` is a separator placeholder (end of hostname, end of a
+            // path segment, ...) rather than a literal character; we
+            // don't model separator semantics precisely, so just drop it.
+            filter.pattern = remainder.replace('^', "");
+        } else {
+            filter.pattern = pattern_part.to_string();
+        }
+
+        Some(filter)
+    }
+
+    fn matches_resource_type(&self, request_type: &str) -> bool {
+        if self.excluded_resource_types.iter().any(|t| t == request_type) {
+            return false;
+        }
+        if self.resource_types.is_empty() {
+            return true;
+        }
+        self.resource_types.iter().any(|t| t == request_type)
+    }
+
+    fn matches_domain_option(&self, source_hostname: &str) -> bool {
+        matches_domain_options(source_hostname, &self.domains)
+    }
+
+    fn matches_pattern(&self, request: &Request, regex_manager: &RegexManager) -> bool {
+        if let Some(hostname) = &self.hostname {
+            if !hostname_matches_domain(&request.hostname, hostname) {
+                return false;
+            }
+        }
+        if self.pattern.is_empty() {
+            return true;
+        }
+        regex_manager.matches(self.id, &self.pattern, &request.url)
+    }
+
+    /// Whether this filter applies to `request` at all, independent of
+    /// what the filter does once matched (block, except, rewrite, ...).
+    /// Pattern matching goes through `regex_manager` so that any regex the
+    /// pattern needs is compiled (and cached) lazily, on first use.
+    pub fn matches(&self, request: &Request, regex_manager: &RegexManager) -> bool {
+        if let Some(third_party) = self.third_party {
+            if request.third_party != Some(third_party) {
+                return false;
+            }
+        }
+        if !request.request_type.is_empty() && !self.matches_resource_type(&request.request_type) {
+            return false;
+        }
+        if !request.source_hostname.is_empty() && !self.matches_domain_option(&request.source_hostname) {
+            return false;
+        }
+        self.matches_pattern(request, regex_manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hostname_anchor() {
+        let filter = NetworkFilter::parse("||doubleclick.net^$third-party").unwrap();
+        assert_eq!(filter.hostname.as_deref(), Some("doubleclick.net"));
+        assert_eq!(filter.third_party, Some(true));
+    }
+
+    #[test]
+    fn parses_exception() {
+        let filter = NetworkFilter::parse("@@||example.com/ads.js$script").unwrap();
+        assert!(filter.is_exception);
+        assert!(filter.matches_resource_type("script"));
+        assert!(!filter.matches_resource_type("image"));
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        assert!(NetworkFilter::parse("! a comment").is_none());
+        assert!(NetworkFilter::parse("").is_none());
+        assert!(NetworkFilter::parse("[Adblock Plus 2.0]").is_none());
+    }
+
+    #[test]
+    fn parses_removeparam_name() {
+        let filter = NetworkFilter::parse("||youtube.com^$removeparam=utm_source").unwrap();
+        let removeparam = filter.removeparam.unwrap();
+        assert!(matches!(removeparam.matcher, RemoveParamMatcher::Name(ref n) if n == "utm_source"));
+        assert!(!removeparam.invert);
+    }
+
+    #[test]
+    fn parses_removeparam_regex() {
+        let filter = NetworkFilter::parse(r"||youtube.com^$removeparam=/^utm_/").unwrap();
+        let removeparam = filter.removeparam.unwrap();
+        assert!(matches!(removeparam.matcher, RemoveParamMatcher::Regex(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_removeparam_name() {
+        assert!(RemoveParamFilter::parse("not valid!").is_none());
+    }
+
+    #[test]
+    fn removeparam_rewrites_query() {
+        let filter = RemoveParamFilter::parse("utm_source").unwrap();
+        let url = url::Url::parse(
+            "https://www.youtube.com/youtubei/v1/log_event?alt=json&utm_source=abc",
+        )
+        .unwrap();
+        let rewritten = filter.rewrite(&url).unwrap();
+        assert_eq!(
+            rewritten,
+            "https://www.youtube.com/youtubei/v1/log_event?alt=json"
+        );
+    }
+
+    #[test]
+    fn removeparam_inverted_keeps_only_matching_name() {
+        let filter = RemoveParamFilter::parse("~alt").unwrap();
+        let url = url::Url::parse(
+            "https://www.youtube.com/youtubei/v1/log_event?alt=json&utm_source=abc",
+        )
+        .unwrap();
+        let rewritten = filter.rewrite(&url).unwrap();
+        assert_eq!(rewritten, "https://www.youtube.com/youtubei/v1/log_event?alt=json");
+    }
+
+    #[test]
+    fn parses_redirect() {
+        let filter = NetworkFilter::parse("||example.com/tracker.js$redirect=noop.js").unwrap();
+        assert_eq!(filter.redirect.as_deref(), Some("noop.js"));
+        assert!(!filter.redirect_rule);
+    }
+
+    #[test]
+    fn parses_redirect_rule() {
+        let filter = NetworkFilter::parse("||example.com/tracker.js$redirect-rule=noop.js").unwrap();
+        assert_eq!(filter.redirect.as_deref(), Some("noop.js"));
+        assert!(filter.redirect_rule);
+    }
+
+    #[test]
+    fn removeparam_noop_without_match() {
+        let filter = RemoveParamFilter::parse("utm_source").unwrap();
+        let url = url::Url::parse("https://www.youtube.com/watch?v=abc").unwrap();
+        assert!(filter.rewrite(&url).is_none());
+    }
+}
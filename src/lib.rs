@@ -0,0 +1,17 @@
+//! A native Rust implementation of the Adblock Plus filter syntax, ported
+//! from the list-matching core of Brave's ad-blocking engine.
+
+pub mod blocker;
+#[cfg(feature = "remote-catalog")]
+pub mod catalog;
+pub mod cosmetic_filter_cache;
+pub mod engine;
+pub mod filters;
+pub mod lists;
+pub mod regex_manager;
+pub mod request;
+pub mod resources;
+#[cfg(feature = "object-pooling")]
+pub(crate) mod tokens;
+pub mod url_parser;
+pub mod utils;
@@ -0,0 +1,69 @@
+//! Splits a raw filter list into its network and cosmetic filter lines and
+//! parses each into a structured filter.
+
+use crate::filters::cosmetic::CosmeticFilter;
+use crate::filters::network::NetworkFilter;
+
+/// Parses `rules` into network and cosmetic filters. Lines that don't
+/// parse as either (comments, metadata headers, or currently-unsupported
+/// syntax) are silently skipped, matching the lenient behavior filter
+/// lists expect from consumers.
+pub fn parse_filters(
+    rules: &[String],
+    load_network_filters: bool,
+    load_cosmetic_filters: bool,
+    _debug: bool,
+) -> (Vec<NetworkFilter>, Vec<CosmeticFilter>) {
+    let mut network_filters = Vec::new();
+    let mut cosmetic_filters = Vec::new();
+
+    for rule in rules {
+        let trimmed = rule.trim();
+        if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.starts_with('[') {
+            continue;
+        }
+
+        let is_cosmetic = trimmed.contains("##") || trimmed.contains("#@#");
+        if is_cosmetic {
+            if load_cosmetic_filters {
+                if let Some(filter) = CosmeticFilter::parse(trimmed) {
+                    cosmetic_filters.push(filter);
+                }
+            }
+        } else if load_network_filters {
+            if let Some(filter) = NetworkFilter::parse(trimmed) {
+                network_filters.push(filter);
+            }
+        }
+    }
+
+    (network_filters, cosmetic_filters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separates_network_and_cosmetic_lines() {
+        let rules = vec![
+            "||doubleclick.net^".to_string(),
+            "example.com##.ad-banner".to_string(),
+            "! a comment".to_string(),
+        ];
+        let (network, cosmetic) = parse_filters(&rules, true, true, false);
+        assert_eq!(network.len(), 1);
+        assert_eq!(cosmetic.len(), 1);
+    }
+
+    #[test]
+    fn respects_load_flags() {
+        let rules = vec![
+            "||doubleclick.net^".to_string(),
+            "example.com##.ad-banner".to_string(),
+        ];
+        let (network, cosmetic) = parse_filters(&rules, true, false, false);
+        assert_eq!(network.len(), 1);
+        assert_eq!(cosmetic.len(), 0);
+    }
+}
@@ -0,0 +1,259 @@
+//! Lazily compiles and caches the regexes network filters need, instead of
+//! compiling every filter's pattern up front. Most patterns in a large
+//! list are never exercised, so compiling only on first match saves both
+//! startup time and memory; [`RegexManagerDiscardPolicy`] bounds how much
+//! of that cache sticks around.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+/// Bounds for [`RegexManager`]'s cache: an absolute entry count, a
+/// time-since-last-use threshold, or both. `None` in either field means
+/// that bound is not enforced.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegexManagerDiscardPolicy {
+    pub max_cached_count: Option<usize>,
+    pub discard_unused_after: Option<Duration>,
+}
+
+struct CacheEntry {
+    regex: Regex,
+    last_used: Instant,
+    usage_count: u64,
+}
+
+/// `true` if `pattern` needs an actual `Regex` to match (wildcards or a
+/// `/regex/` literal); plain literal patterns are matched as a substring
+/// without ever touching the cache.
+fn needs_regex(pattern: &str) -> bool {
+    (pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/')) || pattern.contains('*')
+}
+
+/// Converts `*` into a regex wildcard and escapes everything else, unless
+/// the pattern is already a full `/regex/` literal.
+fn compile_pattern_regex(pattern: &str) -> Option<Regex> {
+    if pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+        return Regex::new(&pattern[1..pattern.len() - 1]).ok();
+    }
+    let mut regex_str = String::from("(?i)");
+    for part in pattern.split('*') {
+        regex_str.push_str(&regex::escape(part));
+        regex_str.push_str(".*");
+    }
+    // trim the trailing ".*" added after the final segment
+    regex_str.truncate(regex_str.len() - 2);
+    Regex::new(&regex_str).ok()
+}
+
+/// Holds one compiled-regex cache entry per filter id. Uses interior
+/// mutability (an `RwLock`) so that matching can stay behind `&self`, the
+/// same as it was back when every regex was compiled eagerly at load
+/// time.
+pub struct RegexManager {
+    cache: RwLock<HashMap<u64, CacheEntry>>,
+    policy: RegexManagerDiscardPolicy,
+    /// Token buffers for the `Blocker`'s per-request literal-matching fast
+    /// path (see [`RegexManager::matches`]) are recycled through this pool
+    /// instead of being freshly allocated for every request, when
+    /// `object-pooling` is enabled.
+    #[cfg(feature = "object-pooling")]
+    token_pool: lifeguard::Pool<Vec<String>>,
+    /// Tokens of the most recently matched `text`, so that the many
+    /// filters checked against the same request URL within one
+    /// `Blocker::check` call share a single tokenization pass.
+    #[cfg(feature = "object-pooling")]
+    token_cache: RwLock<Option<(String, lifeguard::RcRecycled<Vec<String>>)>>,
+}
+
+impl Default for RegexManager {
+    fn default() -> Self {
+        RegexManager::new()
+    }
+}
+
+impl RegexManager {
+    pub fn new() -> RegexManager {
+        RegexManager::with_discard_policy(RegexManagerDiscardPolicy::default())
+    }
+
+    pub fn with_discard_policy(policy: RegexManagerDiscardPolicy) -> RegexManager {
+        RegexManager {
+            cache: RwLock::new(HashMap::new()),
+            policy,
+            #[cfg(feature = "object-pooling")]
+            token_pool: lifeguard::Pool::with_size_and_max(16, 256),
+            #[cfg(feature = "object-pooling")]
+            token_cache: RwLock::new(None),
+        }
+    }
+
+    pub fn set_discard_policy(&mut self, policy: RegexManagerDiscardPolicy) {
+        self.policy = policy;
+    }
+
+    /// Whether `pattern` (from the filter identified by `id`) matches
+    /// `text`, compiling and caching a regex for it on first use. Plain
+    /// literal patterns skip the regex cache entirely; single-word ones
+    /// (no `.`, `/`, etc.) go through the pooled, per-request token cache
+    /// instead of re-lowercasing `text` on every call.
+    pub fn matches(&self, id: u64, pattern: &str, text: &str) -> bool {
+        if !needs_regex(pattern) {
+            #[cfg(feature = "object-pooling")]
+            if crate::tokens::is_single_alnum_token(pattern) {
+                return self.matches_token(pattern, text);
+            }
+            return text.to_lowercase().contains(&pattern.to_lowercase());
+        }
+
+        {
+            let mut cache = self.cache.write().expect("regex cache lock poisoned");
+            if let Some(entry) = cache.get_mut(&id) {
+                entry.last_used = Instant::now();
+                entry.usage_count += 1;
+                return entry.regex.is_match(text);
+            }
+        }
+
+        let Some(regex) = compile_pattern_regex(pattern) else {
+            return false;
+        };
+        let is_match = regex.is_match(text);
+        let mut cache = self.cache.write().expect("regex cache lock poisoned");
+        cache.insert(
+            id,
+            CacheEntry {
+                regex,
+                last_used: Instant::now(),
+                usage_count: 1,
+            },
+        );
+        is_match
+    }
+
+    /// Applies the discard policy now: evicts entries unused for longer
+    /// than `discard_unused_after`, then trims down to `max_cached_count`
+    /// by discarding the least-recently-used entries if it's still over.
+    pub fn cleanup(&self) {
+        let mut cache = self.cache.write().expect("regex cache lock poisoned");
+
+        if let Some(ttl) = self.policy.discard_unused_after {
+            let now = Instant::now();
+            cache.retain(|_, entry| now.duration_since(entry.last_used) < ttl);
+        }
+
+        if let Some(max) = self.policy.max_cached_count {
+            if cache.len() > max {
+                let mut by_last_used: Vec<(u64, Instant)> =
+                    cache.iter().map(|(id, entry)| (*id, entry.last_used)).collect();
+                by_last_used.sort_by_key(|(_, last_used)| *last_used);
+                for (id, _) in by_last_used.into_iter().take(cache.len() - max) {
+                    cache.remove(&id);
+                }
+            }
+        }
+    }
+
+    pub fn cached_count(&self) -> usize {
+        self.cache.read().expect("regex cache lock poisoned").len()
+    }
+
+    /// Matches a single-word literal `pattern` against `text`'s token
+    /// set, tokenizing `text` only when it differs from the last `text`
+    /// seen (the common case across one `Blocker::check` call, which
+    /// tests every filter against the same request URL).
+    #[cfg(feature = "object-pooling")]
+    fn matches_token(&self, pattern: &str, text: &str) -> bool {
+        let pattern = pattern.to_lowercase();
+
+        {
+            let cache = self.token_cache.read().expect("token cache lock poisoned");
+            if let Some((cached_text, tokens)) = cache.as_ref() {
+                if cached_text == text {
+                    return tokens.iter().any(|token| token.contains(&pattern));
+                }
+            }
+        }
+
+        let mut tokens = self.token_pool.new_rc();
+        crate::tokens::tokenize_into(text, &mut tokens);
+        let is_match = tokens.iter().any(|token| token.contains(&pattern));
+
+        let mut cache = self.token_cache.write().expect("token cache lock poisoned");
+        *cache = Some((text.to_string(), tokens));
+        is_match
+    }
+
+    #[cfg(test)]
+    fn usage_count(&self, id: u64) -> Option<u64> {
+        self.cache
+            .read()
+            .expect("regex cache lock poisoned")
+            .get(&id)
+            .map(|entry| entry.usage_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns_never_reach_the_cache() {
+        let manager = RegexManager::new();
+        assert!(manager.matches(1, "ads", "https://example.com/ads/banner"));
+        assert_eq!(manager.cached_count(), 0);
+    }
+
+    #[test]
+    fn wildcard_patterns_compile_once_and_are_cached() {
+        let manager = RegexManager::new();
+        assert!(manager.matches(1, "track*.js", "https://example.com/tracker.js"));
+        assert_eq!(manager.cached_count(), 1);
+        assert!(manager.matches(1, "track*.js", "https://example.com/tracking.js"));
+        assert_eq!(manager.usage_count(1), Some(2));
+    }
+
+    #[test]
+    fn regex_literal_pattern_matches() {
+        let manager = RegexManager::new();
+        assert!(manager.matches(1, "/^https:\\/\\/example\\.com\\/ads?/", "https://example.com/ads"));
+    }
+
+    #[test]
+    fn cleanup_evicts_entries_past_the_ttl() {
+        let manager = RegexManager::with_discard_policy(RegexManagerDiscardPolicy {
+            max_cached_count: None,
+            discard_unused_after: Some(Duration::from_secs(0)),
+        });
+        manager.matches(1, "track*.js", "https://example.com/tracker.js");
+        assert_eq!(manager.cached_count(), 1);
+        manager.cleanup();
+        assert_eq!(manager.cached_count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "object-pooling")]
+    fn single_word_literals_match_via_the_token_cache() {
+        let manager = RegexManager::new();
+        assert!(manager.matches(1, "ads", "https://example.com/myads/banner"));
+        assert!(!manager.matches(2, "tracker", "https://example.com/myads/banner"));
+        // Same `text` as filter 1, exercising the per-text token cache hit.
+        assert!(manager.matches(3, "banner", "https://example.com/myads/banner"));
+    }
+
+    #[test]
+    fn cleanup_trims_to_max_cached_count() {
+        let manager = RegexManager::with_discard_policy(RegexManagerDiscardPolicy {
+            max_cached_count: Some(1),
+            discard_unused_after: None,
+        });
+        manager.matches(1, "foo*.js", "https://example.com/foo.js");
+        manager.matches(2, "bar*.js", "https://example.com/bar.js");
+        assert_eq!(manager.cached_count(), 2);
+        manager.cleanup();
+        assert_eq!(manager.cached_count(), 1);
+    }
+}
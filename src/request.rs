@@ -0,0 +1,136 @@
+//! Parses raw URL strings into the hostname/domain pairs the blocker
+//! matches filters against.
+
+use thiserror::Error;
+
+use crate::url_parser::{parse_hostname_domain, parse_url, UrlParser};
+
+#[derive(Debug, Error)]
+pub enum RequestError {
+    #[error("could not parse url")]
+    UrlParseError,
+}
+
+/// A request to be matched against the filter set, along with the page
+/// that triggered it. Built either directly by [`Request::parse_url`] (for
+/// callers that only have raw URL strings) or by
+/// [`Request::from_hostnames`] (for callers that already resolved hostnames
+/// themselves, e.g. from a previous call).
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub url: String,
+    pub hostname: String,
+    pub domain: String,
+    pub source_hostname: String,
+    pub source_domain: String,
+    pub request_type: String,
+    pub third_party: Option<bool>,
+}
+
+impl Request {
+    /// Parses `url` on its own, with no source/page context. Useful for
+    /// callers that want to resolve a hostname and domain once and reuse
+    /// them across repeated calls to
+    /// [`crate::engine::Engine::check_network_urls_with_hostnames`].
+    pub fn parse_url(url: &str) -> Result<Request, RequestError> {
+        let parsed = parse_url(url).ok_or(RequestError::UrlParseError)?;
+        let hostname = parsed.host_str().unwrap_or("").to_string();
+        let domain = parse_hostname_domain(&hostname);
+        Ok(Request {
+            url: url.to_string(),
+            hostname,
+            domain,
+            source_hostname: String::new(),
+            source_domain: String::new(),
+            request_type: String::new(),
+            third_party: None,
+        })
+    }
+
+    pub fn new(url: &str, source_url: &str, request_type: &str) -> Result<Request, RequestError> {
+        let url_parsed = parse_url(url).ok_or(RequestError::UrlParseError)?;
+        let hostname = url_parsed.host_str().unwrap_or("").to_string();
+        let domain = parse_hostname_domain(&hostname);
+
+        let (source_hostname, source_domain) = match parse_url(source_url) {
+            Some(source_parsed) => {
+                let source_hostname = source_parsed.host_str().unwrap_or("").to_string();
+                let source_domain = parse_hostname_domain(&source_hostname);
+                (source_hostname, source_domain)
+            }
+            None => (String::new(), String::new()),
+        };
+
+        let third_party = if source_hostname.is_empty() {
+            None
+        } else {
+            Some(source_domain != domain)
+        };
+
+        Ok(Request {
+            url: url.to_string(),
+            hostname,
+            domain,
+            source_hostname,
+            source_domain,
+            request_type: request_type.to_string(),
+            third_party,
+        })
+    }
+
+    pub fn from_hostnames(
+        url: &str,
+        hostname: &str,
+        source_hostname: &str,
+        request_type: &str,
+        third_party: Option<bool>,
+    ) -> Request {
+        Request {
+            url: url.to_string(),
+            domain: parse_hostname_domain(hostname),
+            hostname: hostname.to_string(),
+            source_domain: parse_hostname_domain(source_hostname),
+            source_hostname: source_hostname.to_string(),
+            request_type: request_type.to_string(),
+            third_party,
+        }
+    }
+}
+
+impl UrlParser for Request {
+    fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    fn domain(&self) -> &str {
+        &self.domain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hostname_and_domain() {
+        let req = Request::parse_url("https://www.youtube.com/watch?v=1").unwrap();
+        assert_eq!(req.hostname(), "www.youtube.com");
+        assert_eq!(req.domain(), "youtube.com");
+    }
+
+    #[test]
+    fn rejects_unparseable_urls() {
+        assert!(Request::parse_url("not a url").is_err());
+    }
+
+    #[test]
+    fn computes_third_party() {
+        let req = Request::new(
+            "https://tracker.example/pixel.gif",
+            "https://www.other-site.com/",
+            "image",
+        )
+        .unwrap();
+        assert_eq!(req.third_party, Some(true));
+    }
+}
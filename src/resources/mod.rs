@@ -0,0 +1,207 @@
+//! Named substitute resources (scripts, images, ...) that `$redirect` and
+//! `##+js(...)` filters can reference by name.
+
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+
+/// The handful of content types redirect resources actually come in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MimeType {
+    ApplicationJavascript,
+    TextHtml,
+    TextCss,
+    TextPlain,
+    ImageGif,
+    ImagePng,
+    Other(String),
+}
+
+impl MimeType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MimeType::ApplicationJavascript => "application/javascript",
+            MimeType::TextHtml => "text/html",
+            MimeType::TextCss => "text/css",
+            MimeType::TextPlain => "text/plain",
+            MimeType::ImageGif => "image/gif",
+            MimeType::ImagePng => "image/png",
+            MimeType::Other(s) => s,
+        }
+    }
+
+}
+
+impl std::str::FromStr for MimeType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<MimeType, Self::Err> {
+        Ok(match s {
+            "application/javascript" | "text/javascript" => MimeType::ApplicationJavascript,
+            "text/html" => MimeType::TextHtml,
+            "text/css" => MimeType::TextCss,
+            "text/plain" => MimeType::TextPlain,
+            "image/gif" => MimeType::ImageGif,
+            "image/png" => MimeType::ImagePng,
+            other => MimeType::Other(other.to_string()),
+        })
+    }
+}
+
+/// Whether a [`Resource`]'s stored `content` is already base64-encoded, or
+/// is raw source text that still needs encoding when it's turned into a
+/// `data:` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceContent {
+    Base64(String),
+    Raw(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub kind: MimeType,
+    pub content: ResourceContent,
+}
+
+impl Resource {
+    /// The resource's raw (decoded) source text, e.g. for scriptlet
+    /// templating.
+    pub fn decoded_content(&self) -> Option<String> {
+        match &self.content {
+            ResourceContent::Raw(s) => Some(s.clone()),
+            ResourceContent::Base64(b64) => BASE64
+                .decode(b64)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok()),
+        }
+    }
+
+    fn base64_content(&self) -> String {
+        match &self.content {
+            ResourceContent::Base64(b64) => b64.clone(),
+            ResourceContent::Raw(raw) => BASE64.encode(raw),
+        }
+    }
+
+    /// A ready-to-use `data:` URL embedding this resource's content.
+    pub fn as_data_url(&self) -> String {
+        format!("data:{};base64,{}", self.kind.as_str(), self.base64_content())
+    }
+}
+
+/// Drops a resource name's extension (`noop.js` -> `noop`), so lookups can
+/// tolerate either spelling.
+fn strip_extension(name: &str) -> &str {
+    name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(name)
+}
+
+/// Holds every [`Resource`] loaded via [`crate::engine::Engine::use_resources`],
+/// indexed by name and alias so that `$redirect=noop.js` and
+/// `##+js(noop, ...)` filters can resolve their targets.
+#[derive(Default)]
+pub struct ResourceStorage {
+    resources: HashMap<String, Resource>,
+    aliases: HashMap<String, String>,
+}
+
+impl ResourceStorage {
+    pub fn from_resources(resources: Vec<Resource>) -> ResourceStorage {
+        let mut storage = ResourceStorage::default();
+        for resource in resources {
+            for alias in &resource.aliases {
+                storage
+                    .aliases
+                    .entry(alias.clone())
+                    .or_insert_with(|| resource.name.clone());
+            }
+            let stem = strip_extension(&resource.name);
+            if stem != resource.name {
+                storage
+                    .aliases
+                    .entry(stem.to_string())
+                    .or_insert_with(|| resource.name.clone());
+            }
+            storage.resources.insert(resource.name.clone(), resource);
+        }
+        storage
+    }
+
+    /// Looks a resource up by name, alias, or either with its extension
+    /// dropped (`noop` and `noop.js` both resolve to the same resource).
+    pub fn get(&self, name: &str) -> Option<&Resource> {
+        self.resources
+            .get(name)
+            .or_else(|| self.aliases.get(name).and_then(|canon| self.resources.get(canon)))
+            .or_else(|| {
+                let stem = strip_extension(name);
+                if stem == name {
+                    None
+                } else {
+                    self.resources
+                        .get(stem)
+                        .or_else(|| self.aliases.get(stem).and_then(|canon| self.resources.get(canon)))
+                }
+            })
+    }
+
+    /// Resolves a `$redirect`/`$redirect-rule` target to a usable `data:`
+    /// URL.
+    pub fn get_redirect_resource(&self, name: &str) -> Option<String> {
+        self.get(name).map(|r| r.as_data_url())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop_js() -> Resource {
+        Resource {
+            name: "noop.js".to_string(),
+            aliases: vec!["noop".to_string()],
+            kind: MimeType::ApplicationJavascript,
+            content: ResourceContent::Raw("(function() {})()".to_string()),
+        }
+    }
+
+    #[test]
+    fn resolves_by_exact_name() {
+        let storage = ResourceStorage::from_resources(vec![noop_js()]);
+        assert!(storage.get("noop.js").is_some());
+    }
+
+    #[test]
+    fn resolves_by_alias() {
+        let storage = ResourceStorage::from_resources(vec![noop_js()]);
+        assert!(storage.get("noop").is_some());
+    }
+
+    #[test]
+    fn resolves_stem_without_registered_alias() {
+        let resource = Resource {
+            name: "hijacktest.js".to_string(),
+            aliases: vec![],
+            kind: MimeType::ApplicationJavascript,
+            content: ResourceContent::Raw("console.log(1)".to_string()),
+        };
+        let storage = ResourceStorage::from_resources(vec![resource]);
+        assert!(storage.get("hijacktest").is_some());
+        assert!(storage.get("hijacktest.js").is_some());
+    }
+
+    #[test]
+    fn builds_data_url() {
+        let storage = ResourceStorage::from_resources(vec![noop_js()]);
+        let url = storage.get_redirect_resource("noop.js").unwrap();
+        assert!(url.starts_with("data:application/javascript;base64,"));
+    }
+
+    #[test]
+    fn missing_resource_returns_none() {
+        let storage = ResourceStorage::from_resources(vec![noop_js()]);
+        assert!(storage.get("does-not-exist").is_none());
+    }
+}
@@ -0,0 +1,51 @@
+//! Splits request URLs into lowercase alphanumeric tokens so that simple,
+//! single-word literal patterns can be matched against a request's token
+//! set instead of re-scanning (and re-lowering) the whole URL on every
+//! filter checked against it.
+
+/// `true` if `pattern` has no separator characters, meaning it can only
+/// ever appear inside a single token of any text it matches against (a
+/// token is itself a maximal alphanumeric run, so a pattern containing
+/// `.`, `/`, etc. could never be fully contained in one).
+pub(crate) fn is_single_alnum_token(pattern: &str) -> bool {
+    !pattern.is_empty() && pattern.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Appends the lowercase alphanumeric runs of `text` to `buf` as separate
+/// tokens, without clearing it first (callers reuse a pooled buffer and
+/// are expected to clear it themselves between requests).
+pub(crate) fn tokenize_into(text: &str, buf: &mut Vec<String>) {
+    let mut current = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            current.push(c.to_ascii_lowercase());
+        } else if !current.is_empty() {
+            buf.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        buf.push(current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_non_alphanumeric_boundaries() {
+        let mut tokens = Vec::new();
+        tokenize_into("https://Ads.Example.com/track?id=1", &mut tokens);
+        assert_eq!(
+            tokens,
+            vec!["https", "ads", "example", "com", "track", "id", "1"]
+        );
+    }
+
+    #[test]
+    fn single_alnum_token_detection() {
+        assert!(is_single_alnum_token("ads"));
+        assert!(!is_single_alnum_token("ads.example"));
+        assert!(!is_single_alnum_token(""));
+    }
+}
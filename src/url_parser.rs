@@ -0,0 +1,30 @@
+//! Trait implemented by anything that can answer hostname/domain questions
+//! about a URL, so that [`crate::blocker::Blocker`] can be handed either a
+//! freshly-parsed [`crate::request::Request`] or pre-parsed hostname data
+//! supplied by a caller that already did the work.
+
+use addr::parse_domain_name;
+use url::Url;
+
+pub trait UrlParser {
+    /// The full hostname, e.g. `www.example.com`.
+    fn hostname(&self) -> &str;
+    /// The registrable domain, e.g. `example.com`, derived from the public
+    /// suffix list. Falls back to the full hostname if it can't be
+    /// resolved (IP literals, single-label hosts, etc).
+    fn domain(&self) -> &str;
+}
+
+/// Extracts the registrable domain from a hostname using the public
+/// suffix list, falling back to the hostname itself.
+pub fn parse_hostname_domain(hostname: &str) -> String {
+    parse_domain_name(hostname)
+        .ok()
+        .and_then(|parsed| parsed.root())
+        .map(|root| root.to_string())
+        .unwrap_or_else(|| hostname.to_string())
+}
+
+pub(crate) fn parse_url(url: &str) -> Option<Url> {
+    Url::parse(url).ok()
+}
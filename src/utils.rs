@@ -0,0 +1,34 @@
+//! Small standalone helpers shared across the crate.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Reads each file path in `paths` and flattens them into a single list of
+/// filter rule lines, in order, skipping blank lines. Comment lines (`!`)
+/// are kept as-is; [`crate::lists::parse_filters`] is responsible for
+/// recognizing and discarding them.
+pub fn rules_from_lists(paths: &[String]) -> Vec<String> {
+    let mut rules = Vec::new();
+    for path in paths {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Could not open {}: {}", path, e);
+                continue;
+            }
+        };
+        let reader = BufReader::new(file);
+        for line in reader.lines() {
+            match line {
+                Ok(line) => {
+                    let trimmed = line.trim();
+                    if !trimmed.is_empty() {
+                        rules.push(trimmed.to_string());
+                    }
+                }
+                Err(e) => eprintln!("Error reading line from {}: {}", path, e),
+            }
+        }
+    }
+    rules
+}
@@ -1,3 +1,12 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::bool_comparison,
+    clippy::len_zero,
+    clippy::useless_vec,
+    clippy::single_component_path_imports,
+    clippy::unnecessary_unwrap
+)]
+
 extern crate adblock;
 
 use adblock::blocker::{Blocker, BlockerOptions};